@@ -0,0 +1,154 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A small fixed-capacity, least-recently-used cache, used to avoid re-fetching
+//! unchanged storage reads on a [`Client`](crate::Client) that's reused across calls.
+//! Entries also expire after an optional TTL, since nothing here otherwise knows
+//! whether a cached key has since changed on chain.
+
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::hash::Hash;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+/// A cache that holds at most `capacity` entries, evicting the least recently used one
+/// once full, and treating any entry older than `ttl` (if set) as a miss.
+pub struct LruCache<K, V> {
+    capacity: usize,
+    ttl: Option<Duration>,
+    order: VecDeque<K>,
+    entries: HashMap<K, (Instant, V)>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
+    /// Creates an empty cache holding at most `capacity` entries, with no expiry.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_ttl(capacity, None)
+    }
+
+    /// Creates an empty cache holding at most `capacity` entries, each expiring `ttl`
+    /// after it was inserted.
+    pub fn with_ttl(capacity: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            capacity,
+            ttl,
+            order: VecDeque::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns a clone of the cached value for `key`, if present and not expired,
+    /// marking it as the most recently used entry.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let expired = match self.entries.get(key) {
+            Some((inserted_at, _)) => {
+                self.ttl.map_or(false, |ttl| inserted_at.elapsed() > ttl)
+            }
+            None => return None,
+        };
+        if expired {
+            self.remove(key);
+            return None
+        }
+        self.touch(key);
+        self.entries.get(key).map(|(_, value)| value.clone())
+    }
+
+    /// Inserts `value` for `key`, evicting the least recently used entry first if the
+    /// cache is already at capacity.
+    pub fn put(&mut self, key: K, value: V) {
+        if self
+            .entries
+            .insert(key.clone(), (Instant::now(), value))
+            .is_some()
+        {
+            self.touch(&key);
+            return
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drops every cached entry, e.g. after submitting an extrinsic that may have
+    /// mutated storage this cache can't otherwise know to invalidate.
+    pub fn clear(&mut self) {
+        self.order.clear();
+        self.entries.clear();
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.entries.remove(key);
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+        }
+    }
+
+    fn touch(&mut self, key: &K) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            if let Some(key) = self.order.remove(pos) {
+                self.order.push_back(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_entry_past_capacity() {
+        let mut cache = LruCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.get(&"a"); // touch "a" so "b" becomes the least recently used
+        cache.put("c", 3);
+
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"c"), Some(3));
+    }
+
+    #[test]
+    fn expires_entries_past_ttl() {
+        let mut cache = LruCache::with_ttl(8, Some(Duration::from_millis(10)));
+        cache.put("key", "value");
+        assert_eq!(cache.get(&"key"), Some("value"));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(cache.get(&"key"), None);
+    }
+
+    #[test]
+    fn clear_drops_all_entries() {
+        let mut cache = LruCache::new(8);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.clear();
+
+        assert_eq!(cache.get(&"a"), None);
+        assert_eq!(cache.get(&"b"), None);
+    }
+}
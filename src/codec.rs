@@ -0,0 +1,48 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers for building calls from pieces that are already SCALE encoded, and for
+//! encoding arguments using their `Compact` representation where a pallet expects it.
+
+use parity_scale_codec::{
+    Encode,
+    HasCompact,
+};
+
+/// An already SCALE-encoded value, e.g. a call resolved from [`Metadata`](crate::metadata::Metadata).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Encoded(pub Vec<u8>);
+
+impl Encode for Encoded {
+    fn encode(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+}
+
+/// Wraps `value` so that it is SCALE-encoded using its `Compact` representation, as
+/// most balance and index arguments in srml calls require.
+pub fn compact<T: HasCompact>(value: T) -> Compact<T> {
+    Compact(value)
+}
+
+/// A value that SCALE-encodes as `Compact<T>`. Constructed via [`compact`].
+pub struct Compact<T: HasCompact>(T);
+
+impl<T: HasCompact> Encode for Compact<T> {
+    fn encode(&self) -> Vec<u8> {
+        <<T as HasCompact>::Type as From<&T>>::from(&self.0).encode()
+    }
+}
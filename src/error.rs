@@ -0,0 +1,101 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Error handling for subxt.
+
+use jsonrpc_core_client::RpcError;
+use parity_scale_codec::Error as CodecError;
+
+use crate::metadata::MetadataError;
+
+/// Error type.
+#[derive(Debug)]
+pub enum Error {
+    /// Codec error.
+    Codec(CodecError),
+    /// Rpc error.
+    Rpc(RpcError),
+    /// Serde json error.
+    Serialization(serde_json::Error),
+    /// Metadata error.
+    Metadata(MetadataError),
+    /// Some other error.
+    Other(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Codec(e) => write!(f, "Scale codec error: {}", e),
+            Error::Rpc(e) => write!(f, "Rpc error: {}", e),
+            Error::Serialization(e) => write!(f, "Serde json error: {}", e),
+            Error::Metadata(e) => write!(f, "Metadata error: {}", e),
+            Error::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// Returns true if this looks like a transport-level failure (the request never
+    /// made it to a healthy node), as opposed to an application-level rejection (bad
+    /// params, a reverted extrinsic, ...) that a perfectly healthy connection can still
+    /// deliver. Used to decide whether a shared RPC connection should be re-dialed.
+    pub(crate) fn is_connection_error(&self) -> bool {
+        match self {
+            Error::Rpc(RpcError::JsonRpcError(_)) => false,
+            Error::Rpc(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<CodecError> for Error {
+    fn from(error: CodecError) -> Self {
+        Error::Codec(error)
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(error: RpcError) -> Self {
+        Error::Rpc(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Serialization(error)
+    }
+}
+
+impl From<MetadataError> for Error {
+    fn from(error: MetadataError) -> Self {
+        Error::Metadata(error)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(error: &str) -> Self {
+        Error::Other(error.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(error: String) -> Self {
+        Error::Other(error)
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Builds signers from BIP39 mnemonics, the same way the node CLI derives accounts,
+//! so callers don't have to generate or embed raw keypairs themselves.
+
+use bip39::{
+    Language,
+    Mnemonic,
+};
+use hmac::Hmac;
+use pbkdf2::pbkdf2;
+use sha2::Sha512;
+use substrate_primitives::crypto::{
+    DeriveJunction,
+    Pair,
+};
+
+use crate::error::Error;
+
+const PBKDF2_ROUNDS: u32 = 2048;
+const SEED_LEN: usize = 64;
+
+/// Constructs [`Pair`]s from BIP39 mnemonic/seed phrases.
+pub struct Keystore;
+
+impl Keystore {
+    /// Parses `phrase` as `<mnemonic>(//hard/soft)*`: validates the mnemonic's
+    /// checksum, derives the 64-byte seed via PBKDF2-HMAC-SHA512 salted with
+    /// `"mnemonic" + password`, then applies any `//hard` or `/soft` junctions to the
+    /// root key, exactly as the node CLI's `--suri` parsing does.
+    pub fn from_phrase<P: Pair>(phrase: &str, password: Option<&str>) -> Result<P, Error> {
+        let (mnemonic, path) = split_derivation_path(phrase);
+        let mnemonic = Mnemonic::from_phrase(mnemonic, Language::English)
+            .map_err(|e| Error::Other(format!("Invalid mnemonic: {}", e)))?;
+
+        let salt = format!("mnemonic{}", password.unwrap_or(""));
+        let mut seed = [0u8; SEED_LEN];
+        // Substrate derives from the mnemonic's raw entropy, not its phrase text, so
+        // that derivation matches `subkey`/the node CLI rather than plain BIP-39.
+        pbkdf2::<Hmac<Sha512>>(
+            mnemonic.entropy(),
+            salt.as_bytes(),
+            PBKDF2_ROUNDS,
+            &mut seed,
+        );
+
+        let root = P::from_seed_slice(&seed[..32])
+            .map_err(|e| Error::Other(format!("Invalid seed: {:?}", e)))?;
+        root.derive(path.into_iter())
+            .map_err(|e| Error::Other(format!("Invalid derivation path: {:?}", e)))
+    }
+}
+
+/// Splits `<mnemonic>//hard/soft/...` into the bare mnemonic and its junctions. A `//`
+/// prefix on a segment makes it a hard junction, a single `/` a soft one. A segment
+/// that parses as a `u64` becomes a numeric index junction, as in the node CLI.
+fn split_derivation_path(phrase: &str) -> (&str, Vec<DeriveJunction>) {
+    let mnemonic_end = phrase.find('/').unwrap_or_else(|| phrase.len());
+    let (mnemonic, mut rest) = phrase.split_at(mnemonic_end);
+
+    let mut junctions = Vec::new();
+    while !rest.is_empty() {
+        let hard = rest.starts_with("//");
+        rest = rest.trim_start_matches('/');
+        let end = rest.find('/').unwrap_or_else(|| rest.len());
+        let (segment, tail) = rest.split_at(end);
+        if !segment.is_empty() {
+            let junction = match segment.parse::<u64>() {
+                Ok(index) => DeriveJunction::soft(index),
+                Err(_) => DeriveJunction::soft(segment),
+            };
+            junctions.push(if hard { junction.harden() } else { junction });
+        }
+        rest = tail;
+    }
+    (mnemonic, junctions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use substrate_primitives::sr25519;
+
+    const PHRASE: &str =
+        "bottom drive obey lake curtain smoke basket hold race lopez tackle fight";
+
+    // Mirrors `substrate-bip39`'s `mini_secret_from_entropy`, which is what
+    // `subkey`/the node CLI actually run: PBKDF2-HMAC-SHA512 over the mnemonic's raw
+    // entropy, not its phrase text.
+    fn expected_seed(phrase: &str, password: Option<&str>) -> [u8; SEED_LEN] {
+        let mnemonic = Mnemonic::from_phrase(phrase, Language::English).unwrap();
+        let salt = format!("mnemonic{}", password.unwrap_or(""));
+        let mut seed = [0u8; SEED_LEN];
+        pbkdf2::<Hmac<Sha512>>(mnemonic.entropy(), salt.as_bytes(), PBKDF2_ROUNDS, &mut seed);
+        seed
+    }
+
+    #[test]
+    fn from_phrase_matches_subkey_derivation() {
+        let pair = Keystore::from_phrase::<sr25519::Pair>(PHRASE, None).unwrap();
+        let expected =
+            sr25519::Pair::from_seed_slice(&expected_seed(PHRASE, None)[..32]).unwrap();
+        assert_eq!(pair.public(), expected.public());
+    }
+
+    #[test]
+    fn from_phrase_does_not_derive_from_phrase_text() {
+        let pair = Keystore::from_phrase::<sr25519::Pair>(PHRASE, None).unwrap();
+
+        // The bug this guards against: deriving from `mnemonic.phrase().as_bytes()`
+        // instead of `mnemonic.entropy()` silently produces a different key.
+        let mnemonic = Mnemonic::from_phrase(PHRASE, Language::English).unwrap();
+        let mut wrong_seed = [0u8; SEED_LEN];
+        pbkdf2::<Hmac<Sha512>>(
+            mnemonic.phrase().as_bytes(),
+            b"mnemonic",
+            PBKDF2_ROUNDS,
+            &mut wrong_seed,
+        );
+        let wrong = sr25519::Pair::from_seed_slice(&wrong_seed[..32]).unwrap();
+
+        assert_ne!(pair.public(), wrong.public());
+    }
+
+    #[test]
+    fn from_phrase_applies_derivation_path() {
+        let root = Keystore::from_phrase::<sr25519::Pair>(PHRASE, None).unwrap();
+        let derived =
+            Keystore::from_phrase::<sr25519::Pair>(&format!("{}//hard/soft", PHRASE), None)
+                .unwrap();
+        assert_ne!(root.public(), derived.public());
+    }
+}
@@ -20,6 +20,12 @@
 #![deny(missing_docs)]
 //#![deny(warnings)]
 
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::Duration;
+
 use futures::future::{
     self,
     Either,
@@ -32,6 +38,7 @@ use parity_scale_codec::{
     Decode,
     Encode,
 };
+use runtime_primitives::generic::Era;
 use runtime_primitives::traits::StaticLookup;
 use substrate_primitives::{
     storage::{
@@ -43,6 +50,8 @@ use substrate_primitives::{
 use url::Url;
 
 use crate::{
+    cache::LruCache,
+    keystore::Keystore,
     rpc::{
         MapStream,
         Rpc,
@@ -53,12 +62,29 @@ use crate::{
     },
 };
 pub use error::Error;
+pub use keystore::Keystore;
+pub use value::Value;
 
+mod cache;
 mod codec;
 mod error;
+mod keystore;
 mod metadata;
 mod rpc;
 pub mod srml;
+mod value;
+
+/// Number of recent storage reads a [`Client`] keeps cached, to avoid re-fetching a
+/// key on every call within [`STORAGE_CACHE_TTL`] of fetching it from the node (a
+/// cache hit does not extend that window). The cache is also
+/// dropped in full whenever a submitted extrinsic completes, since that may have
+/// mutated storage this cache has no way to otherwise know about.
+const STORAGE_CACHE_CAPACITY: usize = 128;
+
+/// How long a cached storage read is trusted before it's treated as a miss.
+const STORAGE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+const LOCK_POISONED: &str = "lock is poisoned";
 
 /// Captures data for when an extrinsic is successfully included in a block
 #[derive(Debug)]
@@ -69,6 +95,11 @@ pub struct ExtrinsicSuccess<T: System> {
     pub extrinsic: T::Hash,
     /// List of events.
     pub events: Vec<T::Event>,
+    /// The era the extrinsic was signed with.
+    pub era: Era,
+    /// The checkpoint block hash `era` is anchored to (the genesis hash if `era` is
+    /// `Era::Immortal`).
+    pub checkpoint: T::Hash,
 }
 
 fn connect<T: System>(url: &Url) -> impl Future<Item = Rpc<T>, Error = Error> {
@@ -106,42 +137,133 @@ impl<T: System> ClientBuilder<T> {
         connect::<T>(&url).and_then(|rpc| {
             rpc.metadata()
                 .join(rpc.genesis_hash())
-                .map(|(metadata, genesis_hash)| {
+                .map(move |(metadata, genesis_hash)| {
                     Client {
-                        url,
-                        genesis_hash,
-                        metadata,
+                        inner: Arc::new(Shared {
+                            url,
+                            genesis_hash,
+                            metadata,
+                            rpc: Mutex::new(Some(rpc)),
+                            storage_cache: Mutex::new(LruCache::with_ttl(
+                                STORAGE_CACHE_CAPACITY,
+                                Some(STORAGE_CACHE_TTL),
+                            )),
+                        }),
                     }
                 })
         })
     }
 }
 
-/// Client to interface with a substrate node.
-pub struct Client<T: System> {
+struct Shared<T: System> {
     url: Url,
     genesis_hash: T::Hash,
     metadata: Metadata,
+    rpc: Mutex<Option<Rpc<T>>>,
+    storage_cache: Mutex<LruCache<StorageKey, Option<Vec<u8>>>>,
+}
+
+/// Client to interface with a substrate node. Cheaply cloneable: every clone shares
+/// the same underlying RPC connection, reconnecting transparently if it drops.
+pub struct Client<T: System> {
+    inner: Arc<Shared<T>>,
 }
 
 impl<T: System> Clone for Client<T> {
     fn clone(&self) -> Self {
         Self {
-            url: self.url.clone(),
-            genesis_hash: self.genesis_hash.clone(),
-            metadata: self.metadata.clone(),
+            inner: self.inner.clone(),
         }
     }
 }
 
 impl<T: System + 'static> Client<T> {
+    /// Returns the shared RPC connection opened by [`ClientBuilder::build`], dialing a
+    /// new one if a previous call against it failed with a transport-level error.
     fn connect(&self) -> impl Future<Item = Rpc<T>, Error = Error> {
-        connect(&self.url)
+        let cached = self.inner.rpc.lock().expect(LOCK_POISONED).clone();
+        match cached {
+            Some(rpc) => Either::A(future::ok(rpc)),
+            None => {
+                let client = self.clone();
+                Either::B(connect(&self.inner.url).map(move |rpc| {
+                    *client.inner.rpc.lock().expect(LOCK_POISONED) = Some(rpc.clone());
+                    rpc
+                }))
+            }
+        }
+    }
+
+    /// Drops the cached connection, so that the next call dials a fresh one instead of
+    /// reusing one that just failed.
+    fn invalidate(&self) {
+        *self.inner.rpc.lock().expect(LOCK_POISONED) = None;
+    }
+
+    /// Drops every cached storage read, e.g. after submitting an extrinsic that may
+    /// have mutated storage this cache has no way to otherwise know about.
+    fn clear_storage_cache(&self) {
+        self.inner
+            .storage_cache
+            .lock()
+            .expect(LOCK_POISONED)
+            .clear();
+    }
+
+    /// Runs `f` against the shared connection, invalidating it if `f` fails with a
+    /// transport-level error so a dropped websocket doesn't keep failing every
+    /// subsequent call. An application-level rejection (bad params, a reverted
+    /// extrinsic, ...) leaves the connection cached, since the socket is still healthy.
+    fn with_rpc<F, I>(&self, f: F) -> impl Future<Item = I, Error = Error>
+    where
+        F: FnOnce(Rpc<T>) -> Box<dyn Future<Item = I, Error = Error> + Send> + Send + 'static,
+        I: Send + 'static,
+    {
+        let client = self.clone();
+        self.connect().and_then(move |rpc| {
+            f(rpc).map_err(move |err| {
+                if err.is_connection_error() {
+                    client.invalidate();
+                }
+                err
+            })
+        })
     }
 
     /// Returns the chain metadata.
     pub fn metadata(&self) -> &Metadata {
-        &self.metadata
+        &self.inner.metadata
+    }
+
+    /// Fetches the raw bytes at `key`, consulting the bounded storage cache first and
+    /// filling it on a miss.
+    fn fetch_raw_cached(
+        &self,
+        key: StorageKey,
+    ) -> impl Future<Item = Option<Vec<u8>>, Error = Error> {
+        if let Some(cached) = self
+            .inner
+            .storage_cache
+            .lock()
+            .expect(LOCK_POISONED)
+            .get(&key)
+        {
+            return Either::A(future::ok(cached))
+        }
+        let client = self.clone();
+        let cache_key = key.clone();
+        Either::B(
+            self.with_rpc(move |rpc| Box::new(rpc.storage_raw(key)))
+                .map(move |data| {
+                    client
+                        .inner
+                        .storage_cache
+                        .lock()
+                        .expect(LOCK_POISONED)
+                        .put(cache_key, data.clone());
+                    data
+                }),
+        )
     }
 
     /// Fetch a StorageKey.
@@ -149,7 +271,10 @@ impl<T: System + 'static> Client<T> {
         &self,
         key: StorageKey,
     ) -> impl Future<Item = Option<V>, Error = Error> {
-        self.connect().and_then(|rpc| rpc.storage::<V>(key))
+        self.fetch_raw_cached(key).and_then(|data| {
+            data.map(|d| Decode::decode(&mut d.as_slice()).map_err(Into::into))
+                .transpose()
+        })
     }
 
     /// Fetch a StorageKey or return the default.
@@ -169,26 +294,64 @@ impl<T: System + 'static> Client<T> {
         self.fetch(key).map(|value| value.unwrap_or_default())
     }
 
+    /// Fetches the raw, undecoded bytes of `storage` in `module`, resolving its
+    /// `StorageKey` purely from metadata rather than a hand-written module. `keys` is
+    /// empty for a plain value, or a single `Value` for a map lookup.
+    pub fn fetch_raw(
+        &self,
+        module: &str,
+        storage: &str,
+        keys: &[Value],
+    ) -> impl Future<Item = Option<Vec<u8>>, Error = Error> {
+        let key = self
+            .metadata()
+            .module(module)
+            .map_err(Error::from)
+            .and_then(|module| module.storage(storage).map_err(Error::from))
+            .and_then(|storage| storage.key_raw(keys));
+        let client = self.clone();
+        future::result(key).and_then(move |key| client.fetch_raw_cached(key))
+    }
+
     /// Subscribe to events.
     pub fn subscribe_events(
         &self,
     ) -> impl Future<Item = MapStream<StorageChangeSet<T::Hash>>, Error = Error> {
-        self.connect().and_then(|rpc| rpc.subscribe_events())
+        self.with_rpc(|rpc| Box::new(rpc.subscribe_events()))
     }
 
     /// Subscribe to new blocks.
     pub fn subscribe_blocks(
         &self,
     ) -> impl Future<Item = MapStream<T::Header>, Error = Error> {
-        self.connect().and_then(|rpc| rpc.subscribe_blocks())
+        self.with_rpc(|rpc| Box::new(rpc.subscribe_blocks()))
     }
 
     /// Subscribe to finalized blocks.
     pub fn subscribe_finalized_blocks(
         &self,
     ) -> impl Future<Item = MapStream<T::Header>, Error = Error> {
-        self.connect()
-            .and_then(|rpc| rpc.subscribe_finalized_blocks())
+        self.with_rpc(|rpc| Box::new(rpc.subscribe_finalized_blocks()))
+    }
+
+    /// Subscribe to `keys` changing in every finalized block, rather than every
+    /// best-chain block. Each change set is fetched at the exact finalized block hash
+    /// via `state_queryStorageAt`, and finality gaps (the subscription jumping several
+    /// block numbers at once) are backfilled so no finalized block is skipped.
+    pub fn subscribe_finalized_storage(
+        &self,
+        keys: Vec<StorageKey>,
+    ) -> impl Future<Item = MapStream<StorageChangeSet<T::Hash>>, Error = Error> {
+        self.with_rpc(move |rpc| Box::new(rpc.subscribe_finalized_storage(keys)))
+    }
+
+    /// Subscribe to the `System.Events` of every finalized block, decoded into
+    /// `T::Event`. Prefer this over [`Client::subscribe_events`] when consumers need to
+    /// act on events, since best-chain blocks can still be reorged away.
+    pub fn subscribe_finalized_events(
+        &self,
+    ) -> impl Future<Item = MapStream<Vec<T::Event>>, Error = Error> {
+        self.with_rpc(|rpc| Box::new(rpc.subscribe_finalized_events()))
     }
 
     /// Create a transaction builder for a private key.
@@ -212,9 +375,29 @@ impl<T: System + 'static> Client<T> {
                 client,
                 nonce,
                 signer,
+                mortal_period: None,
             }
         })
     }
+
+    /// Create a transaction builder for a signer derived from a BIP39 mnemonic/seed
+    /// phrase via [`Keystore::from_phrase`], rather than a pre-built [`Pair`]. This
+    /// lets callers load accounts the same way the node CLI does.
+    pub fn xt_from_phrase<P>(
+        &self,
+        phrase: &str,
+        password: Option<&str>,
+        nonce: Option<T::Index>,
+    ) -> impl Future<Item = XtBuilder<T, P>, Error = Error>
+    where
+        P: Pair,
+        P::Public: Into<T::AccountId> + Into<<T::Lookup as StaticLookup>::Source>,
+        P::Signature: Codec,
+    {
+        let client = self.clone();
+        future::result(Keystore::from_phrase::<P>(phrase, password))
+            .and_then(move |signer| client.xt(signer, nonce))
+    }
 }
 
 /// Transaction builder.
@@ -222,6 +405,7 @@ pub struct XtBuilder<T: System, P> {
     client: Client<T>,
     nonce: T::Index,
     signer: P,
+    mortal_period: Option<u64>,
 }
 
 impl<T: System + 'static, P> XtBuilder<T, P>
@@ -245,32 +429,94 @@ where
         self.nonce = nonce;
     }
 
+    /// Makes subsequent submissions from this builder mortal: the extrinsic expires
+    /// `period` blocks after the finalized checkpoint block fetched at submit time,
+    /// instead of remaining validly replayable forever. `Era::mortal` rounds `period` up
+    /// to a power of two between 4 and 65536 (e.g. 10_000 becomes 16384). This is the
+    /// recommended default for online signing.
+    pub fn set_era(&mut self, period: u64) {
+        self.mortal_period = Some(period);
+    }
+
+    /// Resolves `module.function(args)` purely from metadata (call index and argument
+    /// type ids, including `Compact<..>` wrapping) and submits it, without requiring a
+    /// hand-written module and trait impl for `module`.
+    pub fn call(
+        &mut self,
+        module: &str,
+        function: &str,
+        args: &[Value],
+    ) -> impl Future<Item = T::Hash, Error = Error> {
+        let call = self
+            .metadata()
+            .module(module)
+            .map_err(Error::from)
+            .and_then(|module| module.call_dynamic(function, args));
+        match call {
+            Ok(call) => Either::A(self.submit(call)),
+            Err(err) => Either::B(future::err(err)),
+        }
+    }
+
     /// Submits a transaction to the chain.
-    pub fn submit<C: Encode + Send>(
+    pub fn submit<C: Encode + Send + 'static>(
         &mut self,
         call: C,
     ) -> impl Future<Item = T::Hash, Error = Error> {
         let signer = self.signer.clone();
         let nonce = self.nonce.clone();
-        let genesis_hash = self.client.genesis_hash.clone();
+        let genesis_hash = self.client.inner.genesis_hash.clone();
+        let mortal_period = self.mortal_period;
+        let client = self.client.clone();
         self.set_nonce(nonce + 1.into());
         self.client
-            .connect()
-            .and_then(move |rpc| rpc.submit_extrinsic(signer, call, nonce, genesis_hash))
+            .with_rpc(move |rpc| {
+                Box::new(rpc.submit_extrinsic(signer, call, nonce, genesis_hash, mortal_period))
+            })
+            .map(move |hash| {
+                // The extrinsic may have mutated arbitrary storage; don't keep serving
+                // cached reads that could now be stale.
+                client.clear_storage_cache();
+                hash
+            })
     }
 
     /// Submits transaction to the chain and watch for events.
-    pub fn submit_and_watch<C: Encode + Send>(
+    pub fn submit_and_watch<C: Encode + Send + 'static>(
         &mut self,
         call: C,
     ) -> impl Future<Item = ExtrinsicSuccess<T>, Error = Error> {
         let signer = self.signer.clone();
         let nonce = self.nonce.clone();
-        let genesis_hash = self.client.genesis_hash.clone();
+        let genesis_hash = self.client.inner.genesis_hash.clone();
+        let mortal_period = self.mortal_period;
+        let client = self.client.clone();
         self.set_nonce(nonce + 1.into());
-        self.client.connect().and_then(move |rpc| {
-            rpc.submit_and_watch_extrinsic(signer, call, nonce, genesis_hash)
-        })
+        self.client
+            .with_rpc(move |rpc| {
+                Box::new(rpc.submit_and_watch_extrinsic(
+                    signer,
+                    call,
+                    nonce,
+                    genesis_hash,
+                    mortal_period,
+                ))
+            })
+            .map(move |success| {
+                client.clear_storage_cache();
+                success
+            })
+    }
+
+    /// Sets a mortal era with `period` and submits `call` in one step; equivalent to
+    /// calling [`XtBuilder::set_era`] followed by [`XtBuilder::submit`].
+    pub fn submit_mortal<C: Encode + Send + 'static>(
+        &mut self,
+        period: u64,
+        call: C,
+    ) -> impl Future<Item = T::Hash, Error = Error> {
+        self.set_era(period);
+        self.submit(call)
     }
 }
 
@@ -282,6 +528,10 @@ mod tests {
         BalancesCalls,
         BalancesStore,
     };
+    use crate::srml::utility::{
+        Utility,
+        UtilityCalls,
+    };
     use futures::stream::Stream;
     use parity_scale_codec::Encode;
     use runtime_primitives::generic::Era;
@@ -312,10 +562,10 @@ mod tests {
             srml_system::CheckWeight<node_runtime::Runtime>,
             srml_balances::TakeFees<node_runtime::Runtime>,
         );
-        fn extra(nonce: Self::Index) -> Self::SignedExtra {
+        fn extra(nonce: Self::Index, era: Era) -> Self::SignedExtra {
             (
                 srml_system::CheckGenesis::<node_runtime::Runtime>::new(),
-                srml_system::CheckEra::<node_runtime::Runtime>::from(Era::Immortal),
+                srml_system::CheckEra::<node_runtime::Runtime>::from(era),
                 srml_system::CheckNonce::<node_runtime::Runtime>::from(nonce),
                 srml_system::CheckWeight::<node_runtime::Runtime>::new(),
                 srml_balances::TakeFees::<node_runtime::Runtime>::from(0),
@@ -327,6 +577,8 @@ mod tests {
         type Balance = <node_runtime::Runtime as srml_balances::Trait>::Balance;
     }
 
+    impl Utility for Runtime {}
+
     type Index = <Runtime as System>::Index;
     type AccountId = <Runtime as System>::AccountId;
     type Address = <<Runtime as System>::Lookup as StaticLookup>::Source;
@@ -356,6 +608,66 @@ mod tests {
         rt.block_on(xt.transfer(dest.into(), 10_000)).unwrap();
     }
 
+    #[test]
+    #[ignore] // requires locally running substrate node
+    fn test_tx_transfer_balance_mortal() {
+        let (mut rt, client) = test_setup();
+
+        let signer = AccountKeyring::Alice.pair();
+        let mut xt = rt.block_on(client.xt(signer, None)).unwrap();
+        xt.set_era(64);
+
+        let dest = AccountKeyring::Bob.pair().public();
+        let result = rt.block_on(xt.submit_and_watch(
+            node_runtime::Call::Balances(srml_balances::Call::transfer(dest.into(), 10_000)),
+        ))
+        .unwrap();
+
+        assert_ne!(result.era, Era::Immortal);
+    }
+
+    #[test]
+    #[ignore] // requires locally running substrate node
+    fn test_tx_transfer_balance_mortal_quantized_period() {
+        // 64 is below Era::mortal's quantization threshold (periods > 4096 round the
+        // phase, moving the era's birth block a few blocks before the finalized head
+        // it was constructed from); exercise a period above it so a checkpoint hash
+        // built from the wrong block would actually get caught.
+        let (mut rt, client) = test_setup();
+
+        let signer = AccountKeyring::Alice.pair();
+        let mut xt = rt.block_on(client.xt(signer, None)).unwrap();
+        xt.set_era(10_000);
+
+        let dest = AccountKeyring::Bob.pair().public();
+        let result = rt.block_on(xt.submit_and_watch(
+            node_runtime::Call::Balances(srml_balances::Call::transfer(dest.into(), 10_000)),
+        ))
+        .unwrap();
+
+        assert_ne!(result.era, Era::Immortal);
+    }
+
+    #[test]
+    #[ignore] // requires locally running substrate node
+    fn test_tx_submit_batch() {
+        let (mut rt, client) = test_setup();
+
+        let signer = AccountKeyring::Alice.pair();
+        let mut xt = rt.block_on(client.xt(signer, None)).unwrap();
+
+        let dest = AccountKeyring::Bob.pair().public();
+        let transfer = node_runtime::Call::Balances(srml_balances::Call::transfer(
+            dest.into(),
+            10_000,
+        ));
+        let outcome = rt
+            .block_on(xt.submit_batch_and_watch(vec![transfer.clone(), transfer]))
+            .unwrap();
+
+        assert_eq!(outcome.interrupted_at, None);
+    }
+
     #[test]
     #[ignore] // requires locally running substrate node
     fn test_state_read_free_balance() {
@@ -387,6 +699,17 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    #[ignore] // requires locally running substrate node
+    fn test_chain_subscribe_finalized_events() {
+        let (mut rt, client) = test_setup();
+
+        let stream = rt.block_on(client.subscribe_finalized_events()).unwrap();
+        let (_events, _) = rt
+            .block_on(stream.into_future().map_err(|(e, _)| e))
+            .unwrap();
+    }
+
     #[test]
     #[ignore] // requires locally running substrate node
     fn test_chain_read_metadata() {
@@ -0,0 +1,330 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A queryable view over the runtime metadata returned by `state_getMetadata`, used to
+//! resolve module/call/storage names to the indices and keys needed to talk to a node.
+
+use std::collections::HashMap;
+
+use parity_scale_codec::{
+    Decode,
+    Encode,
+};
+use substrate_primitives::storage::StorageKey;
+use substrate_primitives::{
+    blake2_256,
+    twox_128,
+};
+
+use crate::{
+    codec::Encoded,
+    value::{
+        self,
+        Value,
+    },
+    Error,
+};
+
+/// Error produced when metadata does not contain an expected module, call or storage item.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MetadataError {
+    /// Module not found.
+    ModuleNotFound(String),
+    /// Call not found.
+    CallNotFound(String),
+    /// Storage item not found.
+    StorageNotFound(String),
+    /// Storage type does not match requested type.
+    StorageTypeError,
+    /// Event not found.
+    EventNotFound(String),
+}
+
+impl std::fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MetadataError::ModuleNotFound(m) => write!(f, "Module {} not found", m),
+            MetadataError::CallNotFound(c) => write!(f, "Call {} not found", c),
+            MetadataError::StorageNotFound(s) => write!(f, "Storage {} not found", s),
+            MetadataError::StorageTypeError => {
+                write!(f, "Requested storage type does not match metadata")
+            }
+            MetadataError::EventNotFound(e) => write!(f, "Event {} not found", e),
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+/// How a storage map hashes its key into a `StorageKey`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StorageHasher {
+    /// blake2_256.
+    Blake2_256,
+    /// twox_128.
+    Twox128,
+}
+
+impl StorageHasher {
+    fn hash(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            StorageHasher::Blake2_256 => blake2_256(data).to_vec(),
+            StorageHasher::Twox128 => twox_128(data).to_vec(),
+        }
+    }
+}
+
+/// Metadata for a single storage item.
+#[derive(Clone, Debug)]
+pub struct StorageMetadata {
+    prefix: Vec<u8>,
+    hasher: StorageHasher,
+    is_map: bool,
+    /// The map's key type name, e.g. `"AccountId"` or `"Vec<u8>"`. `None` for a plain
+    /// (non-map) storage item.
+    key_type: Option<String>,
+}
+
+impl StorageMetadata {
+    /// Treats this storage item as a plain value and returns its `StorageKey`.
+    pub fn get_plain(&self) -> Result<StorageKey, MetadataError> {
+        if self.is_map {
+            return Err(MetadataError::StorageTypeError)
+        }
+        Ok(StorageKey(self.hasher.hash(&self.prefix)))
+    }
+
+    /// Treats this storage item as a map and returns a handle that can compute keys for it.
+    pub fn get_map<K: Encode, V: Decode>(&self) -> Result<StorageMap<K, V>, MetadataError> {
+        if !self.is_map {
+            return Err(MetadataError::StorageTypeError)
+        }
+        Ok(StorageMap {
+            prefix: self.prefix.clone(),
+            hasher: self.hasher.clone(),
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Computes the `StorageKey` for this item from dynamically-typed `Value`s, without
+    /// needing a statically-typed `get_plain`/`get_map` call. `keys` must be empty for a
+    /// plain value, or contain exactly the map key for a map.
+    pub fn key_raw(&self, keys: &[Value]) -> Result<StorageKey, Error> {
+        let mut bytes = self.prefix.clone();
+        match (self.is_map, keys) {
+            (false, []) => {}
+            (true, [key]) => {
+                let key_type = self.key_type.as_deref().unwrap_or("");
+                key.encode_as(key_type, &mut bytes)?
+            }
+            (false, _) => {
+                return Err(Error::Other(
+                    "Plain storage items take no keys".to_string(),
+                ))
+            }
+            (true, _) => {
+                return Err(Error::Other(
+                    "Expected exactly one map key".to_string(),
+                ))
+            }
+        }
+        Ok(StorageKey(self.hasher.hash(&bytes)))
+    }
+}
+
+/// A storage map resolved from metadata, able to compute the `StorageKey` for a given key.
+pub struct StorageMap<K, V> {
+    prefix: Vec<u8>,
+    hasher: StorageHasher,
+    _marker: std::marker::PhantomData<(K, V)>,
+}
+
+impl<K: Encode, V: Decode> StorageMap<K, V> {
+    /// Returns the `StorageKey` for `key` in this map.
+    pub fn key(&self, key: K) -> StorageKey {
+        let mut bytes = self.prefix.clone();
+        bytes.extend(key.encode());
+        StorageKey(self.hasher.hash(&bytes))
+    }
+}
+
+/// Metadata for a single call within a module.
+#[derive(Clone, Debug)]
+struct CallMetadata {
+    index: u8,
+    /// Metadata type name of each argument, in declaration order, e.g. `"Compact<Balance>"`.
+    arg_types: Vec<String>,
+}
+
+/// Metadata for a single module (pallet).
+#[derive(Clone, Debug)]
+pub struct ModuleMetadata {
+    index: u8,
+    name: String,
+    calls: HashMap<String, CallMetadata>,
+    storage: HashMap<String, StorageMetadata>,
+    events: HashMap<String, u8>,
+}
+
+impl ModuleMetadata {
+    /// This module's name, as it appears in metadata.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// This module's call index, as used on the wire to prefix an encoded call.
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    fn call_metadata(&self, function: &str) -> Result<&CallMetadata, MetadataError> {
+        self.calls
+            .get(function)
+            .ok_or_else(|| MetadataError::CallNotFound(function.to_string()))
+    }
+
+    /// Looks up a call's index within this module.
+    pub fn call_index(&self, function: &str) -> Result<u8, MetadataError> {
+        self.call_metadata(function).map(|call| call.index)
+    }
+
+    /// Encodes a call to `function` in this module with the already-encoded `args`,
+    /// ready to submit as an extrinsic.
+    pub fn call<A: Encode>(
+        &self,
+        function: &str,
+        args: A,
+    ) -> Result<Encoded, MetadataError> {
+        let fn_index = self.call_index(function)?;
+        let mut bytes = vec![self.index, fn_index];
+        bytes.extend(args.encode());
+        Ok(Encoded(bytes))
+    }
+
+    /// Encodes a call to `function` in this module purely from metadata, SCALE-encoding
+    /// each dynamically-typed `args` value according to the argument type metadata
+    /// records for it (including `Compact<..>` wrapping where required). This lets a
+    /// new pallet be driven without a hand-written module and trait impl.
+    pub fn call_dynamic(&self, function: &str, args: &[Value]) -> Result<Encoded, Error> {
+        let call = self.call_metadata(function)?;
+        let mut bytes = vec![self.index, call.index];
+        bytes.extend(value::encode_args(args, &call.arg_types)?);
+        Ok(Encoded(bytes))
+    }
+
+    /// Looks up a storage item by name.
+    pub fn storage(&self, key: &str) -> Result<&StorageMetadata, MetadataError> {
+        self.storage
+            .get(key)
+            .ok_or_else(|| MetadataError::StorageNotFound(key.to_string()))
+    }
+
+    /// Looks up an event's variant index within this module, e.g. to recognise a
+    /// specific event among the opaque `T::Event`s an extrinsic produced.
+    pub fn event_index(&self, event: &str) -> Result<u8, MetadataError> {
+        self.events
+            .get(event)
+            .copied()
+            .ok_or_else(|| MetadataError::EventNotFound(event.to_string()))
+    }
+}
+
+/// A queryable view over a node's runtime metadata.
+#[derive(Clone, Debug)]
+pub struct Metadata {
+    modules: HashMap<String, ModuleMetadata>,
+}
+
+impl Metadata {
+    /// Looks up a module (pallet) by name.
+    pub fn module<S>(&self, name: S) -> Result<&ModuleMetadata, MetadataError>
+    where
+        S: ToString,
+    {
+        let name = name.to_string();
+        self.modules
+            .get(&name)
+            .ok_or(MetadataError::ModuleNotFound(name))
+    }
+
+    /// Returns `true` if the metadata describes a module with this name, e.g. to check
+    /// whether an optional pallet like `Utility` is present on the connected chain.
+    pub fn has_module(&self, name: &str) -> bool {
+        self.modules.contains_key(name)
+    }
+}
+
+impl std::convert::TryFrom<substrate_metadata::RuntimeMetadataPrefixed> for Metadata {
+    type Error = MetadataError;
+
+    fn try_from(
+        raw: substrate_metadata::RuntimeMetadataPrefixed,
+    ) -> Result<Self, MetadataError> {
+        let modules = raw
+            .modules()
+            .enumerate()
+            .map(|(index, raw_module)| {
+                let calls = raw_module
+                    .calls()
+                    .enumerate()
+                    .map(|(i, call)| {
+                        let arg_types =
+                            call.arguments().map(|arg| arg.ty().to_string()).collect();
+                        (
+                            call.name().to_string(),
+                            CallMetadata {
+                                index: i as u8,
+                                arg_types,
+                            },
+                        )
+                    })
+                    .collect();
+                let storage = raw_module
+                    .storage()
+                    .map(|item| {
+                        let prefix =
+                            format!("{} {}", raw_module.prefix(), item.name()).into_bytes();
+                        (
+                            item.name().to_string(),
+                            StorageMetadata {
+                                prefix,
+                                hasher: item.hasher(),
+                                is_map: item.is_map(),
+                                key_type: item.key_type().map(|ty| ty.to_string()),
+                            },
+                        )
+                    })
+                    .collect();
+                let events = raw_module
+                    .events()
+                    .enumerate()
+                    .map(|(i, event)| (event.name().to_string(), i as u8))
+                    .collect();
+                (
+                    raw_module.name().to_string(),
+                    ModuleMetadata {
+                        index: index as u8,
+                        name: raw_module.name().to_string(),
+                        calls,
+                        storage,
+                        events,
+                    },
+                )
+            })
+            .collect();
+        Ok(Metadata { modules })
+    }
+}
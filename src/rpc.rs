@@ -0,0 +1,543 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Thin wrapper around the generated JSONRPC clients for the `chain`, `state`,
+//! `author` and `system` namespaces.
+
+use std::convert::TryFrom;
+
+use futures::{
+    future::{
+        Either,
+        Future,
+    },
+    stream::Stream,
+};
+use jsonrpc_core_client::{
+    RpcChannel,
+    RpcError,
+};
+use log;
+use parity_scale_codec::{
+    Codec,
+    Decode,
+    Encode,
+};
+use runtime_primitives::generic::{
+    Era,
+    SignedBlock,
+};
+use runtime_primitives::traits::{
+    Header as _,
+    StaticLookup,
+};
+use substrate_primitives::storage::{
+    StorageChangeSet,
+    StorageKey,
+};
+use substrate_primitives::Pair;
+use substrate_rpc::{
+    author::AuthorClient,
+    chain::ChainClient,
+    state::StateClient,
+    system::SystemClient,
+};
+
+use crate::{
+    error::Error,
+    metadata::Metadata,
+    srml::system::System,
+    ExtrinsicSuccess,
+};
+
+/// A boxed stream of decoded values, produced by one of the `subscribe_*` calls.
+pub type MapStream<T> = Box<dyn Stream<Item = T, Error = Error> + Send>;
+
+fn err_into<I, S: Stream<Item = I, Error = RpcError> + Send + 'static>(
+    stream: S,
+) -> MapStream<I> {
+    Box::new(stream.map_err(Into::into))
+}
+
+/// Storage key of the `System.Events` value, shared by the best-chain and finalized
+/// event subscriptions.
+fn events_key() -> StorageKey {
+    StorageKey(substrate_primitives::twox_128(b"System Events").to_vec())
+}
+
+/// Given the previously seen finalized block number (`None` before the first
+/// notification) and the block number of a new `chain_subscribeFinalizedHeads`
+/// notification, returns every block number that must be backfilled, in order. A
+/// notification can jump over several block numbers at once if the subscriber is slow
+/// to poll, so this returns the full gap rather than assuming consecutive numbers.
+fn finalized_block_range(last_seen: Option<u64>, number: u64) -> std::ops::RangeInclusive<u64> {
+    let from = last_seen.map(|n| n + 1).unwrap_or(number);
+    from..=number
+}
+
+/// Client for talking to a substrate node via RPC, built once from a shared
+/// [`RpcChannel`] so every call reuses the same underlying websocket connection.
+#[derive(Clone)]
+pub struct Rpc<T: System> {
+    chain: ChainClient<T::BlockNumber, T::Hash, T::Header, SignedBlock<T::Header>>,
+    state: StateClient<T::Hash>,
+    author: AuthorClient<T::Hash, T::Hash>,
+    system: SystemClient<T::Index>,
+}
+
+impl<T: System> From<RpcChannel> for Rpc<T> {
+    fn from(channel: RpcChannel) -> Self {
+        Self {
+            chain: channel.clone().into(),
+            state: channel.clone().into(),
+            author: channel.clone().into(),
+            system: channel.into(),
+        }
+    }
+}
+
+impl<T: System + 'static> Rpc<T> {
+    /// Fetches and decodes the runtime metadata.
+    pub fn metadata(&self) -> impl Future<Item = Metadata, Error = Error> {
+        self.state
+            .metadata(None)
+            .map_err(Into::into)
+            .and_then(|bytes| {
+                let raw = substrate_metadata::RuntimeMetadataPrefixed::decode(
+                    &mut bytes.0.as_slice(),
+                )?;
+                Metadata::try_from(raw).map_err(Into::into)
+            })
+    }
+
+    /// Fetches the genesis hash.
+    pub fn genesis_hash(&self) -> impl Future<Item = T::Hash, Error = Error> {
+        self.chain
+            .block_hash(Some(0u32.into()))
+            .map(|hash| hash.expect("genesis hash always exists"))
+            .map_err(Into::into)
+    }
+
+    /// Fetches the raw, undecoded bytes stored at `key` at the best block.
+    pub fn storage_raw(
+        &self,
+        key: StorageKey,
+    ) -> impl Future<Item = Option<Vec<u8>>, Error = Error> {
+        self.state
+            .storage(key, None)
+            .map(|data| data.map(|d| d.0))
+            .map_err(Into::into)
+    }
+
+    /// Fetches and decodes the value at `key` at the best block.
+    pub fn storage<V: Decode>(
+        &self,
+        key: StorageKey,
+    ) -> impl Future<Item = Option<V>, Error = Error> {
+        self.state
+            .storage(key, None)
+            .map_err(Into::into)
+            .and_then(|data| {
+                data.map(|d| Decode::decode(&mut d.0.as_slice()).map_err(Into::into))
+                    .transpose()
+            })
+    }
+
+    /// Fetches and decodes the value at `key` as of the block `hash`.
+    pub fn storage_at<V: Decode>(
+        &self,
+        key: StorageKey,
+        hash: T::Hash,
+    ) -> impl Future<Item = Option<V>, Error = Error> {
+        self.state
+            .storage(key, Some(hash))
+            .map_err(Into::into)
+            .and_then(|data| {
+                data.map(|d| Decode::decode(&mut d.0.as_slice()).map_err(Into::into))
+                    .transpose()
+            })
+    }
+
+    /// Returns the hash of the block at `number`, if it exists.
+    pub fn block_hash(
+        &self,
+        number: T::BlockNumber,
+    ) -> impl Future<Item = Option<T::Hash>, Error = Error> {
+        self.chain.block_hash(Some(number.into())).map_err(Into::into)
+    }
+
+    /// Fetches the hash of the current best finalized block.
+    pub fn finalized_head(&self) -> impl Future<Item = T::Hash, Error = Error> {
+        self.chain.finalized_head().map_err(Into::into)
+    }
+
+    /// Fetches the header for `hash`.
+    pub fn header(
+        &self,
+        hash: Option<T::Hash>,
+    ) -> impl Future<Item = Option<T::Header>, Error = Error> {
+        self.chain.header(hash).map_err(Into::into)
+    }
+
+    /// Resolves the `Era` and checkpoint block hash to sign an extrinsic against. With
+    /// no `mortal_period`, returns an immortal era anchored at `genesis_hash`. With
+    /// `Some(period)`, fetches the current finalized header and returns
+    /// `Era::mortal(period, ..)` anchored at the hash of the era's actual birth block
+    /// rather than the finalized head's: for periods above 4096 `Era::mortal` quantizes
+    /// the phase, so the birth block can be a few blocks earlier than the finalized
+    /// head, and the node recomputes `CheckEra`'s additional signed data from the birth
+    /// block's hash, not the one the era was constructed from.
+    pub fn era_checkpoint(
+        &self,
+        genesis_hash: T::Hash,
+        mortal_period: Option<u64>,
+    ) -> impl Future<Item = (Era, T::Hash), Error = Error> {
+        let chain = self.chain.clone();
+        match mortal_period {
+            None => Either::A(futures::future::ok((Era::Immortal, genesis_hash))),
+            Some(period) => {
+                let chain2 = self.chain.clone();
+                Either::B(
+                    self.finalized_head()
+                        .and_then(move |hash| {
+                            chain.header(Some(hash)).map_err(Into::into).map(
+                                move |header| {
+                                    let header =
+                                        header.expect("finalized head always exists");
+                                    let number: u64 = (*header.number()).into();
+                                    (Era::mortal(period, number), number)
+                                },
+                            )
+                        })
+                        .and_then(move |(era, number)| {
+                            let birth = era.birth(number);
+                            chain2
+                                .block_hash(Some((birth as u32).into()))
+                                .map_err(Into::into)
+                                .and_then(move |hash| {
+                                    hash.ok_or_else(|| {
+                                        Error::Other(format!(
+                                            "missing hash for era birth block {}",
+                                            birth
+                                        ))
+                                    })
+                                })
+                                .map(move |hash| (era, hash))
+                        }),
+                )
+            }
+        }
+    }
+
+    /// Fetches the next account nonce/index for `account`.
+    pub fn account_nonce(
+        &self,
+        account: T::AccountId,
+    ) -> impl Future<Item = T::Index, Error = Error>
+    where
+        T::AccountId: serde::Serialize,
+    {
+        self.system.account_next_index(account).map_err(Into::into)
+    }
+
+    /// Subscribes to the `System.Events` storage changes on the best chain. Best-chain
+    /// blocks can be reorged away after this fires; prefer
+    /// [`Rpc::subscribe_finalized_events`] when acting on events matters.
+    pub fn subscribe_events(
+        &self,
+    ) -> impl Future<Item = MapStream<StorageChangeSet<T::Hash>>, Error = Error> {
+        self.state
+            .subscribe_storage(Some(vec![events_key()]))
+            .map(err_into)
+            .map_err(Into::into)
+    }
+
+    /// Subscribes to new best-chain headers.
+    pub fn subscribe_blocks(&self) -> impl Future<Item = MapStream<T::Header>, Error = Error> {
+        self.chain
+            .subscribe_new_heads()
+            .map(err_into)
+            .map_err(Into::into)
+    }
+
+    /// Subscribes to finalized headers.
+    pub fn subscribe_finalized_blocks(
+        &self,
+    ) -> impl Future<Item = MapStream<T::Header>, Error = Error> {
+        self.chain
+            .subscribe_finalized_heads()
+            .map(err_into)
+            .map_err(Into::into)
+    }
+
+    /// Queries `keys` as of the block `hash` via `state_queryStorageAt`.
+    pub fn query_storage_at(
+        &self,
+        keys: Vec<StorageKey>,
+        hash: T::Hash,
+    ) -> impl Future<Item = StorageChangeSet<T::Hash>, Error = Error> {
+        self.state
+            .query_storage_at(keys, Some(hash))
+            .map_err(Into::into)
+            .and_then(|mut changes| {
+                changes.pop().ok_or_else(|| {
+                    Error::Other("state_queryStorageAt returned no change set".into())
+                })
+            })
+    }
+
+    /// Subscribes to `keys` changing in every newly finalized block. Unlike
+    /// [`Rpc::subscribe_events`], every change set yielded here is anchored to a
+    /// finalized block, via `chain_subscribeFinalizedHeads` + `state_queryStorageAt`.
+    /// When the finalized-heads subscription jumps over several block numbers at once
+    /// (a "finality gap"), every intermediate block's hash is looked up with
+    /// `chain_getBlockHash` and queried in turn, so no finalized block is skipped. A
+    /// block whose hash can't be looked up fails the stream rather than being silently
+    /// dropped, since a missing finalized block means missed events.
+    pub fn subscribe_finalized_storage(
+        &self,
+        keys: Vec<StorageKey>,
+    ) -> impl Future<Item = MapStream<StorageChangeSet<T::Hash>>, Error = Error> {
+        let chain = self.chain.clone();
+        let state = self.state.clone();
+        self.subscribe_finalized_blocks().map(move |headers| {
+            let chain = chain.clone();
+            let last_seen: std::sync::Mutex<Option<u64>> = std::sync::Mutex::new(None);
+            let block_numbers = headers.map(move |header| {
+                let number: u64 = (*header.number()).into();
+                let mut last_seen = last_seen.lock().expect("not poisoned");
+                let range = finalized_block_range(*last_seen, number);
+                *last_seen = Some(number);
+                futures::stream::iter_ok::<_, Error>(range.collect::<Vec<_>>())
+            });
+            let keys = keys.clone();
+            let state = state.clone();
+            let change_sets = block_numbers
+                .flatten()
+                .and_then(move |number| {
+                    chain
+                        .block_hash(Some((number as u32).into()))
+                        .map_err(Into::into)
+                        .and_then(move |hash| {
+                            hash.ok_or_else(|| {
+                                Error::Other(format!(
+                                    "missing hash for finalized block {}",
+                                    number
+                                ))
+                            })
+                        })
+                })
+                .and_then(move |hash| {
+                    state
+                        .query_storage_at(keys.clone(), Some(hash))
+                        .map_err(Into::into)
+                        .map(|mut changes| changes.pop())
+                })
+                .filter_map(|change| change);
+            Box::new(change_sets) as MapStream<StorageChangeSet<T::Hash>>
+        })
+    }
+
+    /// Subscribes to `System.Events` in every newly finalized block, decoded into
+    /// `T::Event`. See [`Rpc::subscribe_finalized_storage`] for how finality gaps are
+    /// handled.
+    pub fn subscribe_finalized_events(
+        &self,
+    ) -> impl Future<Item = MapStream<Vec<T::Event>>, Error = Error> {
+        self.subscribe_finalized_storage(vec![events_key()]).map(
+            |change_sets| {
+                let events = change_sets.and_then(|change_set| {
+                    let decoded = change_set
+                        .changes
+                        .into_iter()
+                        .find_map(|(_key, data)| data)
+                        .map(|data| Vec::<T::Event>::decode(&mut data.0.as_slice()))
+                        .transpose()
+                        .map(Option::unwrap_or_default)
+                        .map_err(Into::into);
+                    futures::future::result(decoded)
+                });
+                Box::new(events) as MapStream<Vec<T::Event>>
+            },
+        )
+    }
+
+    fn create_and_sign_extrinsic<P>(
+        &self,
+        signer: P,
+        call: impl Encode,
+        nonce: T::Index,
+        genesis_hash: T::Hash,
+        era: Era,
+        checkpoint: T::Hash,
+    ) -> Result<runtime_primitives::generic::UncheckedExtrinsic<
+        <T::Lookup as StaticLookup>::Source,
+        Vec<u8>,
+        P::Signature,
+        T::SignedExtra,
+    >, Error>
+    where
+        P: Pair,
+        P::Public: Into<<T::Lookup as StaticLookup>::Source>,
+        P::Signature: Codec,
+    {
+        let extra = T::extra(nonce, era);
+        // `CheckGenesis`'s additional signed data is always the genesis hash;
+        // `CheckEra`'s is the hash of the era's checkpoint block, which is the genesis
+        // hash too in the immortal case.
+        let raw_payload = (&call, &extra, genesis_hash, checkpoint);
+        let signature = raw_payload.using_encoded(|payload| signer.sign(payload));
+        Ok(runtime_primitives::generic::UncheckedExtrinsic::new_signed(
+            raw_payload.0.encode(),
+            signer.public().into(),
+            signature,
+            extra,
+        ))
+    }
+
+    /// Signs `call` with `signer` and submits it, returning the extrinsic hash as soon
+    /// as it enters the pool. `mortal_period` configures how many blocks, counted from
+    /// the finalized checkpoint fetched here, the extrinsic stays valid for; `None`
+    /// signs an immortal extrinsic anchored at `genesis_hash`.
+    pub fn submit_extrinsic<P, C: Encode + Send>(
+        &self,
+        signer: P,
+        call: C,
+        nonce: T::Index,
+        genesis_hash: T::Hash,
+        mortal_period: Option<u64>,
+    ) -> impl Future<Item = T::Hash, Error = Error>
+    where
+        P: Pair,
+        P::Public: Into<<T::Lookup as StaticLookup>::Source>,
+        P::Signature: Codec,
+    {
+        let rpc = self.clone();
+        self.era_checkpoint(genesis_hash, mortal_period)
+            .and_then(move |(era, checkpoint)| {
+                futures::future::result(rpc.create_and_sign_extrinsic(
+                    signer, call, nonce, genesis_hash, era, checkpoint,
+                ))
+            })
+            .and_then({
+                let author = self.author.clone();
+                move |extrinsic| {
+                    author
+                        .submit_extrinsic(extrinsic.encode().into())
+                        .map_err(Into::into)
+                }
+            })
+    }
+
+    /// Signs `call` with `signer`, submits it and watches the resulting block for the
+    /// extrinsic's events. See [`Rpc::submit_extrinsic`] for `mortal_period`.
+    pub fn submit_and_watch_extrinsic<P, C: Encode + Send>(
+        &self,
+        signer: P,
+        call: C,
+        nonce: T::Index,
+        genesis_hash: T::Hash,
+        mortal_period: Option<u64>,
+    ) -> impl Future<Item = ExtrinsicSuccess<T>, Error = Error>
+    where
+        P: Pair,
+        P::Public: Into<<T::Lookup as StaticLookup>::Source>,
+        P::Signature: Codec,
+    {
+        let state = self.state.clone();
+        let author = self.author.clone();
+        let rpc = self.clone();
+        self.era_checkpoint(genesis_hash, mortal_period)
+            .and_then(move |(era, checkpoint)| {
+                futures::future::result(rpc.create_and_sign_extrinsic(
+                    signer, call, nonce, genesis_hash, era, checkpoint,
+                ))
+                .map(move |extrinsic| (extrinsic, era, checkpoint))
+            })
+            .and_then(move |(extrinsic, era, checkpoint)| {
+                let extrinsic_hash = T::Hashing::hash_of(&extrinsic.encode());
+                author
+                    .watch_extrinsic(extrinsic.encode().into())
+                    .map_err(Into::<Error>::into)
+                    .and_then(move |stream| {
+                        err_into(stream)
+                            .filter_map(|status| match status {
+                                substrate_rpc::author::TransactionStatus::Finalized(hash) => {
+                                    Some(hash)
+                                }
+                                _ => None,
+                            })
+                            .into_future()
+                            .map_err(|(e, _)| e)
+                            .and_then(move |(block, _)| {
+                                let block = block.expect("watch stream ended without finalizing");
+                                state
+                                    .storage(events_key(), Some(block))
+                                    .map_err(Into::into)
+                                    .and_then(move |data| {
+                                        let events: Vec<T::Event> = data
+                                            .map(|d| Decode::decode(&mut d.0.as_slice()))
+                                            .transpose()?
+                                            .unwrap_or_default();
+                                        Ok(ExtrinsicSuccess {
+                                            block,
+                                            extrinsic: extrinsic_hash,
+                                            events,
+                                            era,
+                                            checkpoint,
+                                        })
+                                    })
+                            })
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finalized_block_range_starts_at_the_first_seen_block() {
+        let range = finalized_block_range(None, 5);
+        assert_eq!(range.collect::<Vec<_>>(), vec![5]);
+    }
+
+    #[test]
+    fn finalized_block_range_backfills_a_finality_gap() {
+        // A subscription that last saw block 5 and is next notified of block 8 must
+        // backfill 6 and 7 too, not just jump straight to 8.
+        let range = finalized_block_range(Some(5), 8);
+        assert_eq!(range.collect::<Vec<_>>(), vec![6, 7, 8]);
+    }
+
+    #[test]
+    fn finalized_block_range_is_a_single_block_when_consecutive() {
+        let range = finalized_block_range(Some(5), 6);
+        assert_eq!(range.collect::<Vec<_>>(), vec![6]);
+    }
+
+    #[test]
+    fn era_birth_can_precede_the_block_it_was_constructed_from_for_large_periods() {
+        // Quantization only kicks in for periods above 4096; era_checkpoint must look
+        // up the hash of era.birth(number), not of `number` itself, once that's true.
+        // Period 10_000 rounds to 16384 (quantize factor 4): block 1_000_001 has phase
+        // 577, quantized down to 576, so the era's birth block is 1_000_000.
+        let number = 1_000_001;
+        let era = Era::mortal(10_000, number);
+        assert_ne!(era.birth(number), number);
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implements support for the srml_balances module.
+use crate::{
+    codec::compact,
+    error::Error,
+    srml::system::System,
+    Client,
+    XtBuilder,
+};
+use futures::future::{
+    self,
+    Future,
+};
+use parity_scale_codec::{
+    Codec,
+    HasCompact,
+};
+use runtime_primitives::traits::{
+    MaybeSerializeDebug,
+    Member,
+    SimpleArithmetic,
+    StaticLookup,
+};
+use substrate_primitives::Pair;
+
+/// The subset of the `srml_balances::Trait` that subxt needs to know about.
+pub trait Balances: System {
+    /// The balance of an account.
+    type Balance: Codec + MaybeSerializeDebug + Member + Default + Copy + HasCompact + Send + Sync + 'static;
+}
+
+/// Adds the `free_balance` storage read to [`Client`].
+pub trait BalancesStore {
+    /// Runtime type.
+    type Balances: Balances;
+
+    /// Returns the free balance of `account`.
+    fn free_balance(
+        &self,
+        account: <Self::Balances as System>::AccountId,
+    ) -> Box<dyn Future<Item = <Self::Balances as Balances>::Balance, Error = Error> + Send>;
+}
+
+impl<T: Balances + 'static> BalancesStore for Client<T> {
+    type Balances = T;
+
+    fn free_balance(
+        &self,
+        account: <T as System>::AccountId,
+    ) -> Box<dyn Future<Item = T::Balance, Error = Error> + Send> {
+        let free_balance = || {
+            Ok(self
+                .metadata()
+                .module("Balances")?
+                .storage("FreeBalance")?
+                .get_map::<T::AccountId, T::Balance>()?
+                .key(account))
+        };
+        let key = match free_balance() {
+            Ok(key) => key,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        Box::new(self.fetch_or_default(key))
+    }
+}
+
+/// Adds the `transfer` call to [`XtBuilder`].
+pub trait BalancesCalls {
+    /// Runtime type.
+    type Balances: Balances;
+
+    /// Transfers `amount` of free balance to `dest`.
+    fn transfer(
+        &mut self,
+        dest: <<Self::Balances as System>::Lookup as StaticLookup>::Source,
+        amount: <Self::Balances as Balances>::Balance,
+    ) -> Box<dyn Future<Item = <Self::Balances as System>::Hash, Error = Error> + Send>;
+}
+
+impl<T: Balances + 'static, P> BalancesCalls for XtBuilder<T, P>
+where
+    P: Pair,
+    P::Public: Into<<<T as System>::Lookup as StaticLookup>::Source>,
+    P::Signature: Codec,
+{
+    type Balances = T;
+
+    fn transfer(
+        &mut self,
+        dest: <T::Lookup as StaticLookup>::Source,
+        amount: T::Balance,
+    ) -> Box<dyn Future<Item = T::Hash, Error = Error> + Send> {
+        let transfer_call = || {
+            Ok(self
+                .metadata()
+                .module("Balances")?
+                .call("transfer", (dest, compact(amount)))?)
+        };
+        let call = match transfer_call() {
+            Ok(call) => call,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        Box::new(self.submit(call))
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implements support for the srml_system module.
+
+use futures::future::Future;
+use parity_scale_codec::Codec;
+use runtime_primitives::generic::Era;
+use runtime_primitives::traits::{
+    Hash,
+    Header,
+    MaybeSerializeDebug,
+    Member,
+    StaticLookup,
+};
+
+use crate::{
+    error::Error,
+    Client,
+};
+
+/// The subset of the `srml_system::Trait` that subxt needs to know about in order to
+/// build and sign extrinsics and decode blocks/events for a given runtime.
+pub trait System: Clone + Sized + Send + Sync + 'static {
+    /// Account index (aka nonce) type.
+    type Index: Codec + MaybeSerializeDebug + Default + Copy + Into<u64> + std::ops::Add<Output = Self::Index> + From<u32> + Send + Sync + 'static;
+
+    /// Block number type.
+    type BlockNumber: Codec
+        + MaybeSerializeDebug
+        + From<u32>
+        + Into<u64>
+        + Copy
+        + Send
+        + Sync
+        + 'static;
+
+    /// Block hash type.
+    type Hash: Codec + MaybeSerializeDebug + Member + Default + Copy + Send + Sync + 'static;
+
+    /// Hashing algorithm.
+    type Hashing: Hash<Output = Self::Hash>;
+
+    /// Account id type.
+    type AccountId: Codec + MaybeSerializeDebug + Member + Send + Sync + 'static;
+
+    /// Lookup from an account id to an address suitable for signing/transfer destinations.
+    type Lookup: StaticLookup<Target = Self::AccountId> + Send + Sync + 'static;
+
+    /// Block header type.
+    type Header: Header<Number = Self::BlockNumber, Hash = Self::Hash> + serde::de::DeserializeOwned + Send + Sync + 'static;
+
+    /// The overarching event type.
+    type Event: Codec + Send + Sync + 'static;
+
+    /// The signed extensions applied to every extrinsic for this runtime.
+    type SignedExtra: Codec + Send + Sync + 'static;
+
+    /// Constructs the signed extensions for `nonce`, checking the extrinsic's mortality
+    /// against `era` (`Era::Immortal` unless the caller opted into a mortal extrinsic).
+    fn extra(nonce: Self::Index, era: Era) -> Self::SignedExtra;
+}
+
+/// Adds the `account_nonce` storage read to [`Client`].
+pub trait SystemStore {
+    /// System type.
+    type System: System;
+
+    /// Returns the account's next nonce/index.
+    fn account_nonce(
+        &self,
+        account: <Self::System as System>::AccountId,
+    ) -> Box<dyn Future<Item = <Self::System as System>::Index, Error = Error> + Send>;
+}
+
+impl<T: System + 'static> SystemStore for Client<T> {
+    type System = T;
+
+    fn account_nonce(
+        &self,
+        account: <T as System>::AccountId,
+    ) -> Box<dyn Future<Item = T::Index, Error = Error> + Send> {
+        Box::new(self.with_rpc(move |rpc| Box::new(rpc.account_nonce(account))))
+    }
+}
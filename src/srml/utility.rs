@@ -0,0 +1,131 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implements support for the srml_utility module.
+use crate::{
+    codec::Encoded,
+    error::Error,
+    srml::system::System,
+    ExtrinsicSuccess,
+    XtBuilder,
+};
+use futures::future::{
+    self,
+    Future,
+};
+use parity_scale_codec::{
+    Codec,
+    Decode,
+    Encode,
+};
+use runtime_primitives::traits::StaticLookup;
+use substrate_primitives::Pair;
+
+/// The subset of the `srml_utility::Trait` that subxt needs to know about.
+pub trait Utility: System {}
+
+/// Outcome of a batch submitted via [`UtilityCalls::submit_batch_and_watch`].
+#[derive(Debug)]
+pub struct BatchOutcome<T: System> {
+    /// The underlying extrinsic result.
+    pub extrinsic: ExtrinsicSuccess<T>,
+    /// The index of the call that interrupted the batch, or `None` if every call in
+    /// the batch completed.
+    pub interrupted_at: Option<u32>,
+}
+
+/// Adds `submit_batch`/`submit_batch_and_watch` to [`XtBuilder`].
+pub trait UtilityCalls {
+    /// Runtime type.
+    type Utility: Utility;
+
+    /// Wraps `calls` in a single `Utility.batch` call and submits it atomically with
+    /// one signature and nonce, instead of submitting each call individually.
+    /// Errors if the connected chain has no `Utility` module.
+    fn submit_batch<C: Encode + Send>(
+        &mut self,
+        calls: Vec<C>,
+    ) -> Box<dyn Future<Item = <Self::Utility as System>::Hash, Error = Error> + Send>;
+
+    /// Like [`UtilityCalls::submit_batch`], but watches for the batch's
+    /// `BatchCompleted`/`BatchInterrupted` event so callers can tell whether, and at
+    /// which index, an inner call failed.
+    fn submit_batch_and_watch<C: Encode + Send>(
+        &mut self,
+        calls: Vec<C>,
+    ) -> Box<dyn Future<Item = BatchOutcome<Self::Utility>, Error = Error> + Send>;
+}
+
+fn encode_batch_calls<C: Encode>(calls: Vec<C>) -> Vec<Encoded> {
+    calls.into_iter().map(|c| Encoded(c.encode())).collect()
+}
+
+impl<T: Utility + 'static, P> UtilityCalls for XtBuilder<T, P>
+where
+    P: Pair,
+    P::Public: Into<<<T as System>::Lookup as StaticLookup>::Source>,
+    P::Signature: Codec,
+{
+    type Utility = T;
+
+    fn submit_batch<C: Encode + Send>(
+        &mut self,
+        calls: Vec<C>,
+    ) -> Box<dyn Future<Item = T::Hash, Error = Error> + Send> {
+        let batch_call = || {
+            Ok(self
+                .metadata()
+                .module("Utility")?
+                .call("batch", encode_batch_calls(calls))?)
+        };
+        let call = match batch_call() {
+            Ok(call) => call,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        Box::new(self.submit(call))
+    }
+
+    fn submit_batch_and_watch<C: Encode + Send>(
+        &mut self,
+        calls: Vec<C>,
+    ) -> Box<dyn Future<Item = BatchOutcome<T>, Error = Error> + Send> {
+        let setup = || {
+            let utility = self.metadata().module("Utility")?;
+            let call = utility.call("batch", encode_batch_calls(calls))?;
+            let interrupted_index = utility.event_index("BatchInterrupted")?;
+            Ok((call, utility.index(), interrupted_index))
+        };
+        let (call, module_index, interrupted_index) = match setup() {
+            Ok(setup) => setup,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        Box::new(self.submit_and_watch(call).map(move |extrinsic| {
+            let interrupted_at = extrinsic.events.iter().find_map(|event| {
+                let bytes = event.encode();
+                if bytes.len() >= 6 && bytes[0] == module_index && bytes[1] == interrupted_index
+                {
+                    u32::decode(&mut &bytes[2..6]).ok()
+                } else {
+                    None
+                }
+            });
+            BatchOutcome {
+                extrinsic,
+                interrupted_at,
+            }
+        }))
+    }
+}
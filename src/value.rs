@@ -0,0 +1,147 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of substrate-subxt.
+//
+// subxt is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// subxt is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with substrate-subxt.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A small dynamically-typed value, used to encode call and storage-key arguments for
+//! pallets that have no hand-written module, driven purely by runtime metadata.
+
+use parity_scale_codec::{
+    Compact,
+    Encode,
+};
+
+use crate::error::Error;
+
+/// A dynamically-typed argument, encoded according to the metadata type name it's
+/// matched against.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// `bool`.
+    Bool(bool),
+    /// `u8`.
+    U8(u8),
+    /// `u16`.
+    U16(u16),
+    /// `u32`.
+    U32(u32),
+    /// `u64`.
+    U64(u64),
+    /// `u128`.
+    U128(u128),
+    /// `Vec<u8>`, e.g. an `AccountId` or raw bytes.
+    Bytes(Vec<u8>),
+    /// `String`.
+    Str(String),
+}
+
+impl Value {
+    /// SCALE-encodes this value as `type_name`, appending it to `bytes`. If `type_name`
+    /// is `Compact<..>`, the value is encoded using its compact representation instead.
+    pub(crate) fn encode_as(&self, type_name: &str, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        let compact = type_name.starts_with("Compact<");
+        match self {
+            Value::Bool(v) => v.encode_to(bytes),
+            Value::U8(v) if compact => Compact(*v).encode_to(bytes),
+            Value::U8(v) => v.encode_to(bytes),
+            Value::U16(v) if compact => Compact(*v).encode_to(bytes),
+            Value::U16(v) => v.encode_to(bytes),
+            Value::U32(v) if compact => Compact(*v).encode_to(bytes),
+            Value::U32(v) => v.encode_to(bytes),
+            Value::U64(v) if compact => Compact(*v).encode_to(bytes),
+            Value::U64(v) => v.encode_to(bytes),
+            Value::U128(v) if compact => Compact(*v).encode_to(bytes),
+            Value::U128(v) => v.encode_to(bytes),
+            Value::Bytes(v) => {
+                if compact {
+                    return Err(Error::Other(format!(
+                        "{} cannot be compact-encoded",
+                        type_name
+                    )))
+                }
+                // Fixed-size types (AccountId, Hash, ...) are encoded as raw bytes,
+                // `Vec<u8>` as a length-prefixed byte vector.
+                if type_name.starts_with("Vec<") {
+                    v.encode_to(bytes)
+                } else {
+                    bytes.extend_from_slice(v)
+                }
+            }
+            Value::Str(v) => v.encode_to(bytes),
+        }
+        Ok(())
+    }
+}
+
+/// Encodes `args` in order against the metadata `arg_types` they are matched to.
+pub(crate) fn encode_args(args: &[Value], arg_types: &[String]) -> Result<Vec<u8>, Error> {
+    if args.len() != arg_types.len() {
+        return Err(Error::Other(format!(
+            "Expected {} arguments, got {}",
+            arg_types.len(),
+            args.len()
+        )))
+    }
+    let mut bytes = Vec::new();
+    for (arg, ty) in args.iter().zip(arg_types) {
+        arg.encode_as(ty, &mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_compact_balance() {
+        let mut bytes = Vec::new();
+        Value::U128(10_000)
+            .encode_as("Compact<Balance>", &mut bytes)
+            .unwrap();
+
+        assert_eq!(bytes, Compact(10_000u128).encode());
+    }
+
+    #[test]
+    fn encodes_vec_u8_with_a_length_prefix() {
+        let mut bytes = Vec::new();
+        Value::Bytes(vec![1, 2, 3])
+            .encode_as("Vec<u8>", &mut bytes)
+            .unwrap();
+
+        assert_eq!(bytes, vec![1u8, 2, 3].encode());
+    }
+
+    #[test]
+    fn encodes_fixed_width_bytes_without_a_length_prefix() {
+        let account = vec![7u8; 32];
+        let mut bytes = Vec::new();
+        Value::Bytes(account.clone())
+            .encode_as("AccountId", &mut bytes)
+            .unwrap();
+
+        assert_eq!(bytes, account);
+    }
+
+    #[test]
+    fn rejects_argument_count_mismatch() {
+        let args = [Value::U8(1), Value::U8(2)];
+        let arg_types = ["u8".to_string()];
+
+        let result = encode_args(&args, &arg_types);
+
+        assert!(result.is_err());
+    }
+}